@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+use tokio_postgres::tls::NoTlsStream;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{AsyncMessage, Client, Config, Connection, NoTls, Row, Socket};
+
+use crate::ident::quote_ident;
+use crate::listen::{Notification, NotificationStream};
+
+/// Owns a `Client` together with the background task that drives its
+/// `Connection`, so callers can't forget to spawn it (and silently lose the
+/// client) the way `main` previously had to.
+pub struct Database {
+    client: Arc<Client>,
+    handle: JoinHandle<()>,
+    notifications: broadcast::Sender<Notification>,
+    subscriptions: Arc<Subscriptions>,
+}
+
+/// Tracks how many live [`NotificationStream`]s are subscribed to each
+/// channel on a `Database`'s shared connection, so `LISTEN`/`UNLISTEN` only
+/// fire on the first subscriber in / last subscriber out. Without this, one
+/// `NotificationStream` dropping would `UNLISTEN` a channel out from under
+/// every other subscriber sharing the same connection.
+pub(crate) struct Subscriptions {
+    client: Arc<Client>,
+    counts: Mutex<HashMap<String, usize>>,
+}
+
+impl Subscriptions {
+    fn new(client: Arc<Client>) -> Self {
+        Subscriptions {
+            client,
+            counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn subscribe(&self, channel: &str) -> Result<(), tokio_postgres::Error> {
+        let mut counts = self.counts.lock().await;
+        let count = counts.entry(channel.to_string()).or_insert(0);
+        if *count == 0 {
+            self.client
+                .batch_execute(&format!("LISTEN {}", quote_ident(channel)))
+                .await?;
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    pub(crate) async fn unsubscribe(&self, channel: &str) {
+        let mut counts = self.counts.lock().await;
+        let Some(count) = counts.get_mut(channel) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(channel);
+            let _ = self
+                .client
+                .batch_execute(&format!("UNLISTEN {}", quote_ident(channel)))
+                .await;
+        }
+    }
+}
+
+/// Drains `connection`'s messages, forwarding `LISTEN`/`NOTIFY` payloads to
+/// `notifications` and logging connection-level errors the way `main` used
+/// to log them directly.
+async fn drive_connection(
+    mut connection: Connection<Socket, NoTlsStream>,
+    notifications: broadcast::Sender<Notification>,
+) {
+    loop {
+        match std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+            Some(Ok(AsyncMessage::Notification(n))) => {
+                let _ = notifications.send(Notification {
+                    channel: n.channel().to_string(),
+                    payload: n.payload().to_string(),
+                    process_id: n.process_id(),
+                });
+            }
+            Some(Ok(_)) => {}
+            Some(Err(e)) => {
+                eprintln!("connection error: {}", e);
+                break;
+            }
+            None => break,
+        }
+    }
+}
+
+impl Database {
+    /// Connects using a libpq-style connection string and spawns the
+    /// connection future, logging any error it returns the same way `main`
+    /// used to.
+    pub async fn connect(params: &str) -> Result<Self, Box<dyn Error>> {
+        let (client, connection) = tokio_postgres::connect(params, NoTls).await?;
+        Ok(Self::spawn(client, connection))
+    }
+
+    /// Connects from an already-built `Config`, for callers that need to set
+    /// fields the connection-string form can't express.
+    pub async fn connect_with_config(config: Config) -> Result<Self, Box<dyn Error>> {
+        let (client, connection) = config.connect(NoTls).await?;
+        Ok(Self::spawn(client, connection))
+    }
+
+    fn spawn(client: Client, connection: Connection<Socket, NoTlsStream>) -> Self {
+        let (notifications, _) = broadcast::channel(128);
+        let handle = tokio::spawn(drive_connection(connection, notifications.clone()));
+        let client = Arc::new(client);
+        Database {
+            subscriptions: Arc::new(Subscriptions::new(Arc::clone(&client))),
+            client,
+            handle,
+            notifications,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, tokio_postgres::Error> {
+        self.client.execute(statement, params).await
+    }
+
+    pub async fn query(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, tokio_postgres::Error> {
+        self.client.query(statement, params).await
+    }
+
+    pub async fn query_one(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, tokio_postgres::Error> {
+        self.client.query_one(statement, params).await
+    }
+
+    /// Exposes the inner client directly for callers (like this crate's CRUD
+    /// helpers) that take `&Client` rather than `&Database`.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Issues `LISTEN channel` (unless another live subscriber already
+    /// triggered it) and returns a stream of notifications pushed to it,
+    /// demultiplexed from the same connection task used for queries.
+    pub async fn subscribe(&self, channel: &str) -> Result<NotificationStream, tokio_postgres::Error> {
+        self.subscriptions.subscribe(channel).await?;
+        Ok(NotificationStream::new(
+            channel.to_string(),
+            Arc::clone(&self.subscriptions),
+            self.notifications.subscribe(),
+        ))
+    }
+
+    /// Shuts down the connection task cleanly and waits for it to finish.
+    pub async fn close(self) {
+        self.handle.abort();
+        let _ = (&mut self.handle).await;
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fmt;
+
+use tokio_postgres::Row;
+
+/// Error returned when a `Row` cannot be converted into a typed struct.
+#[derive(Debug)]
+pub enum FromRowError {
+    /// The row had no column with this name and the field had no `#[row(default)]`.
+    MissingColumn(&'static str),
+    /// The column was present but its SQL type didn't match the Rust field type.
+    TypeMismatch {
+        column: &'static str,
+        source: tokio_postgres::Error,
+    },
+}
+
+impl fmt::Display for FromRowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromRowError::MissingColumn(column) => {
+                write!(f, "column \"{}\" is missing from the row", column)
+            }
+            FromRowError::TypeMismatch { column, source } => {
+                write!(f, "column \"{}\" has an unexpected type: {}", column, source)
+            }
+        }
+    }
+}
+
+impl Error for FromRowError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromRowError::MissingColumn(_) => None,
+            FromRowError::TypeMismatch { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Implemented for structs that can be built from a single `Row` by matching
+/// field names (or `#[row(rename = "...")]` overrides) against column labels.
+///
+/// Normally produced by `#[derive(FromRow)]` from the companion
+/// `tokio-postgres-derive` proc-macro crate (see `tokio-postgres-derive/src/lib.rs`),
+/// the way `serde_derive` backs `serde::Serialize`.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self, FromRowError>;
+}
+
+/// Extension trait so callers can write `row.try_into_row::<User>()` instead
+/// of `User::from_row(&row)`.
+pub trait TryIntoRow {
+    fn try_into_row<T: FromRow>(&self) -> Result<T, FromRowError>;
+}
+
+impl TryIntoRow for Row {
+    fn try_into_row<T: FromRow>(&self) -> Result<T, FromRowError> {
+        T::from_row(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_column_display() {
+        let err = FromRowError::MissingColumn("age");
+        assert_eq!(err.to_string(), "column \"age\" is missing from the row");
+    }
+}
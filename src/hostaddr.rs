@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::net::IpAddr;
+
+use tokio::net::TcpStream;
+use tokio_postgres::config::Host;
+use tokio_postgres::tls::TlsConnect;
+use tokio_postgres::{Client, Config, Connection};
+
+/// A `Config` augmented with pre-resolved IPs so it can skip DNS for some or
+/// all of its `host` entries.
+///
+/// `host=a.example.com,b.example.com hostaddr=10.0.0.1,10.0.0.2` dials
+/// `10.0.0.1` and `10.0.0.2` directly, in order, while still presenting
+/// `a.example.com` / `b.example.com` for TLS SNI, certificate verification,
+/// and SCRAM channel binding.
+pub struct HostAddrConfig {
+    config: Config,
+    hostaddrs: Vec<Option<IpAddr>>,
+}
+
+impl HostAddrConfig {
+    /// Parses a libpq-style connection string, pulling out `hostaddr` (which
+    /// plain `Config` doesn't understand) before handing the rest to it.
+    pub fn parse(params: &str) -> Result<Self, Box<dyn Error>> {
+        let mut hostaddrs = Vec::new();
+        let mut rest = Vec::new();
+
+        for token in params.split_whitespace() {
+            match token.strip_prefix("hostaddr=") {
+                Some(value) => {
+                    for part in value.split(',') {
+                        hostaddrs.push(if part.is_empty() {
+                            None
+                        } else {
+                            Some(part.parse::<IpAddr>()?)
+                        });
+                    }
+                }
+                None => rest.push(token),
+            }
+        }
+
+        Ok(HostAddrConfig {
+            config: rest.join(" ").parse()?,
+            hostaddrs,
+        })
+    }
+
+    /// Pins the `index`-th `host` entry to `addr`, overriding (or adding to)
+    /// whatever `hostaddr=` the connection string carried.
+    pub fn hostaddr(mut self, index: usize, addr: IpAddr) -> Self {
+        if self.hostaddrs.len() <= index {
+            self.hostaddrs.resize(index + 1, None);
+        }
+        self.hostaddrs[index] = Some(addr);
+        self
+    }
+
+    /// Tries each `host`/`hostaddr` pair in order, returning the first
+    /// successful connection. A pair with no `hostaddr` resolves `host`
+    /// through normal DNS and dials only the first address returned; further
+    /// addresses for the same host are not tried.
+    pub async fn connect<T>(
+        &self,
+        tls: T,
+    ) -> Result<(Client, Connection<TcpStream, T::Stream>), Box<dyn Error>>
+    where
+        T: TlsConnect<TcpStream> + Clone,
+    {
+        let hosts = self.config.get_hosts();
+        let ports = self.config.get_ports();
+        let mut last_err: Option<Box<dyn Error>> = None;
+
+        for (i, host) in hosts.iter().enumerate() {
+            let Host::Tcp(hostname) = host else {
+                continue;
+            };
+            let port = ports
+                .get(i)
+                .or_else(|| ports.first())
+                .copied()
+                .unwrap_or(5432);
+
+            let dial_addr = match self.hostaddrs.get(i).copied().flatten() {
+                Some(addr) => addr,
+                None => match tokio::net::lookup_host((hostname.as_str(), port)).await {
+                    Ok(mut addrs) => match addrs.next() {
+                        Some(addr) => addr.ip(),
+                        None => continue,
+                    },
+                    Err(e) => {
+                        last_err = Some(e.into());
+                        continue;
+                    }
+                },
+            };
+
+            let stream = match TcpStream::connect((dial_addr, port)).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    last_err = Some(e.into());
+                    continue;
+                }
+            };
+
+            // A single-host `Config` for just this candidate: `connect_raw`
+            // has no separate "dial here, but SNI/verify as that" parameter,
+            // so it can only get `hostname` right by us handing it a `Config`
+            // whose (sole) `host` is this candidate's, not the original
+            // multi-host one.
+            let candidate_config = self.single_host_config(hostname, port);
+            match candidate_config.connect_raw(stream, tls.clone()).await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    last_err = Some(e.into());
+                    continue;
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| "no host/hostaddr pairs to try".into()))
+    }
+
+    /// Builds a fresh, single-host `Config` carrying `hostname`/`port` plus
+    /// user, password, dbname, options, application name, connect timeout,
+    /// SSL mode, channel binding, target session attrs, and keepalive on/off
+    /// and idle time copied from the original multi-host config, so TLS SNI,
+    /// certificate verification, and SCRAM channel binding use the right
+    /// hostname for this candidate. Settings not in that list are not copied.
+    fn single_host_config(&self, hostname: &str, port: u16) -> Config {
+        let mut config = Config::new();
+        config.host(hostname).port(port);
+
+        if let Some(user) = self.config.get_user() {
+            config.user(user);
+        }
+        if let Some(password) = self.config.get_password() {
+            config.password(password);
+        }
+        if let Some(dbname) = self.config.get_dbname() {
+            config.dbname(dbname);
+        }
+        if let Some(options) = self.config.get_options() {
+            config.options(options);
+        }
+        if let Some(application_name) = self.config.get_application_name() {
+            config.application_name(application_name);
+        }
+        if let Some(&connect_timeout) = self.config.get_connect_timeout() {
+            config.connect_timeout(connect_timeout);
+        }
+        config
+            .ssl_mode(self.config.get_ssl_mode())
+            .channel_binding(self.config.get_channel_binding())
+            .target_session_attrs(self.config.get_target_session_attrs())
+            .keepalives(self.config.get_keepalives())
+            .keepalives_idle(self.config.get_keepalives_idle());
+
+        config
+    }
+}
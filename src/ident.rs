@@ -0,0 +1,12 @@
+/// Quotes `ident` as a Postgres identifier, doubling any embedded `"` so it
+/// can't break out of the quoted form (e.g. into a second statement via
+/// `batch_execute`/`simple_query`, which allow multiple statements).
+pub(crate) fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Quotes `literal` as a Postgres string literal, doubling any embedded `'`
+/// so it can't break out of the quoted form.
+pub(crate) fn quote_literal(literal: &str) -> String {
+    format!("'{}'", literal.replace('\'', "''"))
+}
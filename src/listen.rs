@@ -0,0 +1,72 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::stream::Stream;
+use futures_util::StreamExt;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+use crate::database::Subscriptions;
+
+/// A `LISTEN`/`NOTIFY` payload delivered to a subscriber.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
+    pub process_id: i32,
+}
+
+/// A live subscription to a Postgres channel, created via
+/// `Database::subscribe`. Yields a [`Notification`] each time the server
+/// delivers one on this channel; issues `UNLISTEN` when dropped, unless
+/// another subscriber to the same channel on the same `Database` is still
+/// live.
+pub struct NotificationStream {
+    channel: String,
+    subscriptions: Arc<Subscriptions>,
+    inner: BroadcastStream<Notification>,
+}
+
+impl NotificationStream {
+    pub(crate) fn new(
+        channel: String,
+        subscriptions: Arc<Subscriptions>,
+        receiver: broadcast::Receiver<Notification>,
+    ) -> Self {
+        NotificationStream {
+            channel,
+            subscriptions,
+            inner: BroadcastStream::new(receiver),
+        }
+    }
+}
+
+impl Stream for NotificationStream {
+    type Item = Notification;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Notification>> {
+        loop {
+            match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(n))) if n.channel == self.channel => {
+                    return Poll::Ready(Some(n))
+                }
+                // Other channels sharing the same underlying connection, or a
+                // slow-subscriber lag error: skip and keep polling.
+                Poll::Ready(Some(_)) => continue,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for NotificationStream {
+    fn drop(&mut self) {
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let channel = self.channel.clone();
+        tokio::spawn(async move {
+            subscriptions.unsubscribe(&channel).await;
+        });
+    }
+}
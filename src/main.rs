@@ -1,6 +1,25 @@
 use std::error::Error;
 
-use tokio_postgres::{NoTls, Row};
+mod database;
+mod from_row;
+mod hostaddr;
+mod ident;
+mod listen;
+mod pool;
+mod reconnect;
+mod replication;
+
+use database::Database;
+use from_row::TryIntoRow;
+use tokio_postgres_derive::FromRow;
+
+/// A row of the `users` table.
+#[derive(Debug, FromRow)]
+struct User {
+    id: i32,
+    name: String,
+    age: i32,
+}
 
 // CREATE: Insert a new user into the database
 async fn create_user(
@@ -18,28 +37,25 @@ async fn create_user(
 }
 
 // READ: Get all users from the database
-async fn read_users(client: &tokio_postgres::Client) -> Result<Vec<Row>, Box<dyn Error>> {
+async fn read_users(client: &tokio_postgres::Client) -> Result<Vec<User>, Box<dyn Error>> {
     let rows = client.query("select id, name, age from users", &[]).await?;
-    Ok(rows)
+    Ok(rows
+        .iter()
+        .map(|row| row.try_into_row::<User>())
+        .collect::<Result<Vec<_>, _>>()?)
 }
 
 // Retrieve a user by their ID
 async fn read_user_by_id(
     client: &tokio_postgres::Client,
     id: i32,
-) -> Result<Option<(i32, String, i32)>, Box<dyn Error>> {
+) -> Result<Option<User>, Box<dyn Error>> {
     let rows = client
         .query("select id, name, age from users where id = $1", &[&id])
         .await?;
-    if rows.is_empty() {
-        Ok(None)
-    } else {
-        let row = &rows[0];
-        let id: i32 = row.get(0);
-        let name: String = row.get(1);
-        let age: i32 = row.get(2);
-
-        Ok(Some((id, name, age)))
+    match rows.first() {
+        Some(row) => Ok(Some(row.try_into_row::<User>()?)),
+        None => Ok(None),
     }
 }
 
@@ -65,45 +81,39 @@ async fn delete_user(client: &tokio_postgres::Client, id: i32) -> Result<(), Box
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // Connect to the database
-    let (client, connection) = tokio_postgres::connect(
-        "host=localhost user=postgres dbname=database_name password=secret",
-        NoTls,
-    )
-    .await?;
-
-    // The connection object performs the actual communication with the database,
-    // so spawn it off to run on its own.
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprint!("connection error: {}", e);
-        }
-    });
+    // Connect to the database; `Database` spawns and supervises the
+    // connection task for us, so it's never accidentally dropped.
+    let db =
+        Database::connect("host=localhost user=postgres dbname=database_name password=secret")
+            .await?;
+    let client = db.client();
 
     // CREATE
-    create_user(&client, "Htet Lin Maung", 27).await?;
+    create_user(client, "Htet Lin Maung", 27).await?;
 
     // READ
-    let users = read_users(&client).await?;
+    let users = read_users(client).await?;
     for user in &users {
-        let id: i32 = user.get(0);
-        let name: String = user.get(1);
-        let age: i32 = user.get(2);
-        println!("id: {}, name: {}, age: {}", id, name, age);
+        println!("id: {}, name: {}, age: {}", user.id, user.name, user.age);
     }
 
     // FETCH BY ID (Fetch Alice by her id)
-    if let Some((id, name, age)) = read_user_by_id(&client, 1).await? {
-        println!("Fetched by ID -> id: {}, name: {}, age: {}", id, name, age);
+    if let Some(user) = read_user_by_id(client, 1).await? {
+        println!(
+            "Fetched by ID -> id: {}, name: {}, age: {}",
+            user.id, user.name, user.age
+        );
     } else {
         println!("User not found by given ID");
     }
 
     // UPDATE (Update Alice's age to 31)
-    update_user_age(&client, 1, 31).await?;
+    update_user_age(client, 1, 31).await?;
 
     // DELETE (Delete Alice by id)
-    delete_user(&client, 1).await?;
+    delete_user(client, 1).await?;
+
+    db.close().await;
 
     Ok(())
 }
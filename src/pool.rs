@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{Mutex, Semaphore};
+use tokio_postgres::tls::MakeTlsConnect;
+use tokio_postgres::{Client, Config, Error, Socket};
+
+/// Tuning knobs for a [`Pool`]. All fields have sane defaults via
+/// [`PoolConfig::default`]; override just the ones that matter.
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub max_size: usize,
+    pub min_idle: usize,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_size: 10,
+            min_idle: 0,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(10 * 60),
+            max_lifetime: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+struct Idle {
+    client: Client,
+    handle: tokio::task::JoinHandle<()>,
+    created_at: Instant,
+    idle_since: Instant,
+    // Held for as long as this connection exists, idle or checked out, so
+    // the semaphore actually caps total open connections at `max_size`
+    // instead of just connections currently checked out.
+    permit: tokio::sync::OwnedSemaphorePermit,
+}
+
+/// A lazily-growing pool of `Client`s that share one `Config`/TLS setup.
+///
+/// Generic over the TLS connector the same way [`crate::hostaddr`] is, so a
+/// pool can be used with `NoTls` or with a real TLS connector interchangeably.
+///
+/// Connections whose background task has errored out, or that have sat idle
+/// or alive longer than the configured limits, are discarded instead of
+/// being handed back out.
+pub struct Pool<T> {
+    config: Config,
+    tls: T,
+    pool_config: PoolConfig,
+    idle: Mutex<VecDeque<Idle>>,
+    permits: Arc<Semaphore>,
+}
+
+impl<T> Pool<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+{
+    pub fn new(config: Config, tls: T, pool_config: PoolConfig) -> Arc<Pool<T>> {
+        let min_idle = pool_config.min_idle;
+        let pool = Arc::new(Pool {
+            config,
+            tls,
+            permits: Arc::new(Semaphore::new(pool_config.max_size)),
+            pool_config,
+            idle: Mutex::new(VecDeque::new()),
+        });
+
+        if min_idle > 0 {
+            let warm = Arc::clone(&pool);
+            tokio::spawn(async move { warm.maintain_min_idle().await });
+        }
+
+        pool
+    }
+
+    /// Background task keeping at least `min_idle` connections open and idle,
+    /// so a caller's `get()` doesn't pay a fresh-connect latency hit after
+    /// the pool has been sitting unused.
+    async fn maintain_min_idle(self: Arc<Self>) {
+        loop {
+            let deficit = {
+                let idle = self.idle.lock().await;
+                self.pool_config.min_idle.saturating_sub(idle.len())
+            };
+
+            for _ in 0..deficit {
+                let Ok(permit) = self.permits.clone().try_acquire_owned() else {
+                    break;
+                };
+                match self.config.connect(self.tls.clone()).await {
+                    Ok((client, connection)) => {
+                        let handle = tokio::spawn(async move {
+                            if let Err(e) = connection.await {
+                                eprintln!("connection error: {}", e);
+                            }
+                        });
+                        self.idle.lock().await.push_back(Idle {
+                            client,
+                            handle,
+                            created_at: Instant::now(),
+                            idle_since: Instant::now(),
+                            permit,
+                        });
+                    }
+                    // `permit` drops here, freeing the slot this warm-up
+                    // attempt failed to use.
+                    Err(e) => eprintln!("failed to warm idle connection: {}", e),
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+    }
+
+    /// Checks out a live, health-checked client, opening a new connection if
+    /// none are idle and the pool isn't at `max_size`.
+    pub async fn get(self: &Arc<Self>) -> Result<PooledClient<T>, PoolError> {
+        loop {
+            // An idle connection already holds the permit that accounts for
+            // it, so reusing one must not acquire a second permit — doing so
+            // would let a full `max_size` worth of *new* connections open on
+            // top of however many are sitting idle.
+            let candidate = self.idle.lock().await.pop_front();
+            let Some(idle) = candidate else {
+                let permit = tokio::time::timeout(
+                    self.pool_config.acquire_timeout,
+                    Arc::clone(&self.permits).acquire_owned(),
+                )
+                .await
+                .map_err(|_| PoolError::AcquireTimeout)?
+                .expect("pool semaphore is never closed");
+
+                let (client, connection) = self.config.connect(self.tls.clone()).await?;
+                let handle = tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        eprintln!("connection error: {}", e);
+                    }
+                });
+                return Ok(PooledClient {
+                    pool: Arc::clone(self),
+                    inner: Some(Idle {
+                        client,
+                        handle,
+                        created_at: Instant::now(),
+                        idle_since: Instant::now(),
+                        permit,
+                    }),
+                });
+            };
+
+            let now = Instant::now();
+            if idle.handle.is_finished()
+                || now.duration_since(idle.created_at) > self.pool_config.max_lifetime
+                || now.duration_since(idle.idle_since) > self.pool_config.idle_timeout
+            {
+                idle.handle.abort();
+                continue;
+            }
+
+            if idle.client.simple_query("").await.is_err() {
+                idle.handle.abort();
+                continue;
+            }
+
+            return Ok(PooledClient {
+                pool: Arc::clone(self),
+                inner: Some(idle),
+            });
+        }
+    }
+
+    async fn release(&self, idle: Idle) {
+        if idle.handle.is_finished() {
+            idle.handle.abort();
+            return;
+        }
+        self.idle.lock().await.push_back(Idle {
+            idle_since: Instant::now(),
+            ..idle
+        });
+    }
+}
+
+#[derive(Debug)]
+pub enum PoolError {
+    AcquireTimeout,
+    Connect(Error),
+}
+
+impl std::fmt::Display for PoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PoolError::AcquireTimeout => write!(f, "timed out waiting for an idle connection"),
+            PoolError::Connect(e) => write!(f, "failed to open a pooled connection: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PoolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PoolError::AcquireTimeout => None,
+            PoolError::Connect(e) => Some(e),
+        }
+    }
+}
+
+impl From<Error> for PoolError {
+    fn from(e: Error) -> Self {
+        PoolError::Connect(e)
+    }
+}
+
+/// An RAII guard around a pooled `Client`. Returns its connection to the
+/// pool on drop instead of closing it.
+pub struct PooledClient<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+{
+    pool: Arc<Pool<T>>,
+    inner: Option<Idle>,
+}
+
+impl<T> Deref for PooledClient<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+{
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.inner.as_ref().expect("client taken before drop").client
+    }
+}
+
+impl<T> DerefMut for PooledClient<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+{
+    fn deref_mut(&mut self) -> &mut Client {
+        &mut self.inner.as_mut().expect("client taken before drop").client
+    }
+}
+
+impl<T> Drop for PooledClient<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+{
+    fn drop(&mut self) {
+        let Some(idle) = self.inner.take() else {
+            return;
+        };
+        // `release` awaits the idle-queue lock, which `Drop` can't do
+        // directly, so hand it to a task. The permit travels inside `idle`
+        // and is only freed if `release` discards a dead connection instead
+        // of requeuing it.
+        let pool = Arc::clone(&self.pool);
+        tokio::spawn(async move {
+            pool.release(idle).await;
+        });
+    }
+}
@@ -0,0 +1,183 @@
+use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::RwLock;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Config, Row};
+
+use crate::database::Database;
+
+/// Backoff schedule used by [`ReconnectingClient`] when a transport error is
+/// detected. Backoff grows as `initial_backoff * multiplier^attempt`, capped
+/// at `max_backoff`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        let scaled = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32);
+        Duration::from_secs_f64(scaled).min(self.max_backoff)
+    }
+}
+
+/// Returns `true` for connection-level failures (closed socket, broken pipe)
+/// that are worth retrying, and `false` for SQL-level failures (e.g. a
+/// unique-constraint violation) that should be surfaced immediately.
+fn is_retryable(err: &tokio_postgres::Error) -> bool {
+    err.code().is_none() && err.is_closed()
+}
+
+/// A client that transparently reconnects and re-issues the failed
+/// statement when the background connection dies from a transient network
+/// error, instead of forcing the caller to tear everything down like a plain
+/// `Client` does.
+///
+/// Statements run inside an explicit transaction are not retried: a replayed
+/// transaction could silently re-apply part of a multi-statement sequence,
+/// so [`ReconnectingClient::transaction`] fails fast on any transport error.
+pub struct ReconnectingClient {
+    config: Config,
+    policy: RetryPolicy,
+    // Holds the current connection behind an `Arc` so a query only needs the
+    // lock long enough to clone it, not for the query's whole round-trip; a
+    // plain `Client` already supports concurrent use, and serializing every
+    // call behind the lock would throw that away.
+    current: RwLock<Arc<Database>>,
+}
+
+impl ReconnectingClient {
+    pub async fn connect(config: Config, policy: RetryPolicy) -> Result<Self, Box<dyn Error>> {
+        let current = Database::connect_with_config(config.clone()).await?;
+        Ok(ReconnectingClient {
+            config,
+            policy,
+            current: RwLock::new(Arc::new(current)),
+        })
+    }
+
+    async fn current(&self) -> Arc<Database> {
+        Arc::clone(&self.current.read().await)
+    }
+
+    /// Replaces the current connection with a freshly-dialed one, retrying
+    /// with exponential backoff up to `policy.max_retries` times.
+    async fn reconnect(&self) -> Result<(), Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            match Database::connect_with_config(self.config.clone()).await {
+                Ok(db) => {
+                    *self.current.write().await = Arc::new(db);
+                    return Ok(());
+                }
+                Err(_) if attempt < self.policy.max_retries => {
+                    tokio::time::sleep(self.policy.backoff_for(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            let result = self.current().await.execute(statement, params).await;
+            match result {
+                Ok(rows) => return Ok(rows),
+                Err(e) if is_retryable(&e) && attempt < self.policy.max_retries => {
+                    self.reconnect().await?;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    pub async fn query(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            let result = self.current().await.query(statement, params).await;
+            match result {
+                Ok(rows) => return Ok(rows),
+                Err(e) if is_retryable(&e) && attempt < self.policy.max_retries => {
+                    self.reconnect().await?;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    pub async fn query_one(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            let result = self.current().await.query_one(statement, params).await;
+            match result {
+                Ok(row) => return Ok(row),
+                Err(e) if is_retryable(&e) && attempt < self.policy.max_retries => {
+                    self.reconnect().await?;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Runs `f` inside `BEGIN`/`COMMIT`, rolling back on error. Unlike
+    /// `execute`/`query`/`query_one`, a transport error here is never
+    /// retried and never triggers [`ReconnectingClient::reconnect`]: `f` runs
+    /// against a single connection snapshot taken up front, so reconnecting
+    /// mid-transaction would silently start a new one while the caller
+    /// thinks it's still adding statements to the old one.
+    pub async fn transaction<F, Fut, R>(&self, f: F) -> Result<R, Box<dyn Error>>
+    where
+        F: FnOnce(Arc<Database>) -> Fut,
+        Fut: Future<Output = Result<R, Box<dyn Error>>>,
+    {
+        let db = self.current().await;
+        db.execute("BEGIN", &[]).await?;
+
+        match f(Arc::clone(&db)).await {
+            Ok(value) => {
+                db.execute("COMMIT", &[]).await?;
+                Ok(value)
+            }
+            Err(e) => {
+                // Best-effort: if the connection is already gone there's
+                // nothing to roll back on, and `e` is the error that matters.
+                let _ = db.execute("ROLLBACK", &[]).await;
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! `#[derive(FromRow)]`, the proc-macro half of the `FromRow` trait defined
+//! in the main crate's `from_row` module (mirrors how `serde_derive` backs
+//! `serde::Serialize`). Generates a `from_row` that pulls each field by
+//! column name, honoring `#[row(rename = "...")]` and `#[row(default)]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields};
+
+#[proc_macro_derive(FromRow, attributes(row))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// The actual codegen, kept separate from the `proc_macro::TokenStream`
+/// entry point above so it can be unit tested by calling it directly on a
+/// parsed `DeriveInput`, without going through real macro expansion.
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input,
+            "FromRow can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &data.fields,
+            "FromRow requires named fields",
+        ));
+    };
+
+    let mut field_inits = Vec::with_capacity(fields.named.len());
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let mut column = ident.to_string();
+        let mut default = false;
+
+        for attr in &field.attrs {
+            if !attr.path().is_ident("row") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    column = lit.value();
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    default = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported #[row(..)] attribute"))
+                }
+            })?;
+        }
+
+        field_inits.push(if default {
+            quote! {
+                #ident: match row.columns().iter().position(|c| c.name() == #column) {
+                    ::std::option::Option::Some(idx) => row
+                        .try_get::<_, #ty>(idx)
+                        .map_err(|source| crate::from_row::FromRowError::TypeMismatch { column: #column, source })?,
+                    ::std::option::Option::None => ::std::default::Default::default(),
+                }
+            }
+        } else {
+            quote! {
+                #ident: {
+                    let idx = row
+                        .columns()
+                        .iter()
+                        .position(|c| c.name() == #column)
+                        .ok_or(crate::from_row::FromRowError::MissingColumn(#column))?;
+                    row.try_get::<_, #ty>(idx)
+                        .map_err(|source| crate::from_row::FromRowError::TypeMismatch { column: #column, source })?
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl crate::from_row::FromRow for #name {
+            fn from_row(row: &::tokio_postgres::Row) -> ::std::result::Result<Self, crate::from_row::FromRowError> {
+                ::std::result::Result::Ok(#name {
+                    #(#field_inits),*
+                })
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_str(input: &str) -> String {
+        let parsed: DeriveInput = syn::parse_str(input).unwrap();
+        expand(parsed).unwrap().to_string()
+    }
+
+    #[test]
+    fn plain_field_is_looked_up_by_its_own_name() {
+        let tokens = expand_str(
+            r#"
+            struct User {
+                id: i32,
+            }
+            "#,
+        );
+        assert!(tokens.contains("\"id\""));
+    }
+
+    #[test]
+    fn rename_attribute_changes_the_looked_up_column_name() {
+        let tokens = expand_str(
+            r#"
+            struct User {
+                #[row(rename = "nick_name")]
+                nickname: String,
+            }
+            "#,
+        );
+        assert!(tokens.contains("\"nick_name\""));
+        assert!(!tokens.contains("\"nickname\""));
+    }
+
+    #[test]
+    fn default_attribute_falls_back_instead_of_requiring_the_column() {
+        let tokens = expand_str(
+            r#"
+            struct User {
+                #[row(default)]
+                age: i32,
+            }
+            "#,
+        );
+        assert!(tokens.contains("Default :: default"));
+    }
+
+    #[test]
+    fn non_struct_input_is_rejected() {
+        let parsed: DeriveInput = syn::parse_str("enum User { A, B }").unwrap();
+        assert!(expand(parsed).is_err());
+    }
+
+    #[test]
+    fn tuple_struct_is_rejected() {
+        let parsed: DeriveInput = syn::parse_str("struct User(i32, String);").unwrap();
+        assert!(expand(parsed).is_err());
+    }
+}